@@ -1,19 +1,34 @@
+use crate::PathFilter;
 use anyhow::Result;
 use signal_hook::consts::{SIGINT, SIGTERM};
-use std::ffi::OsStr;
-use std::path::Path;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 pub struct ProcessingContext<'a> {
     pub chunk_size: usize,
-    pub extensions: &'a Option<Vec<&'a OsStr>>,
+    /// Combines the legacy `-e/--extensions` list with `--include`/`--exclude` globs into a
+    /// single predicate deciding which files get processed.
+    pub filter: &'a PathFilter,
     pub input_path: &'a Path,
     pub script: Option<&'a Path>,
+    pub command: Option<&'a str>,
     pub daemon: bool,
     pub sleep_time: u64,
     pub job_slots: Option<usize>,
     pub term: Arc<AtomicBool>,
+    pub watch: bool,
+    pub watch_debounce_ms: u64,
+    pub no_ignore: bool,
+    pub hidden: bool,
+    /// Where the joblog (if any) is opened, and where `--resume` reads it back from.
+    pub joblog: Option<&'a Path>,
+    /// Directory the joblog and any intermediate bookkeeping live in.
+    pub tempdir: &'a Path,
+    /// Paths already recorded as successfully processed in an existing joblog, to be skipped
+    /// when `--resume` is set. Empty when resume is not requested.
+    pub resume_skip: &'a HashSet<PathBuf>,
 }
 
 impl<'a> ProcessingContext<'a> {
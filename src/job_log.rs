@@ -0,0 +1,266 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The outcome recorded for a single file in the job log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobLogOutcome {
+    Processed,
+    Errored,
+    Cancelled,
+}
+
+impl JobLogOutcome {
+    fn as_str(self) -> &'static str {
+        match self {
+            JobLogOutcome::Processed => "processed",
+            JobLogOutcome::Errored => "errored",
+            JobLogOutcome::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// Appends one line per processed file to a joblog, in the style of GNU parallel's `--joblog`,
+/// so an interrupted run can be resumed with `--resume` instead of reprocessing everything.
+///
+/// Writes are serialized behind a `Mutex<BufWriter>` so Rayon workers completing concurrently
+/// don't interleave partial lines.
+pub struct JobLog {
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl JobLog {
+    /// Opens (creating if necessary) the joblog at `path` for appending.
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create joblog directory {}", parent.display()))?;
+            }
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open joblog at {}", path.display()))?;
+
+        Ok(Self {
+            writer: Mutex::new(BufWriter::new(file)),
+        })
+    }
+
+    /// Records one completed file: its path, outcome, exit status (if any), and start/end
+    /// timestamps, as a single tab-separated line.
+    pub fn record(
+        &self,
+        file: &Path,
+        outcome: JobLogOutcome,
+        exit_status: Option<i32>,
+        started_at: SystemTime,
+        ended_at: SystemTime,
+    ) -> Result<()> {
+        let line = format!(
+            "{}\t{}\t{}\t{}\t{}\n",
+            escape_path(file),
+            outcome.as_str(),
+            exit_status
+                .map(|code| code.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            unix_millis(started_at),
+            unix_millis(ended_at),
+        );
+
+        let mut writer = self
+            .writer
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        writer
+            .write_all(line.as_bytes())
+            .and_then(|_| writer.flush())
+            .with_context(|| format!("Failed to write joblog entry for {}", file.display()))
+    }
+}
+
+fn unix_millis(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or(0)
+}
+
+/// Percent-encodes a path's raw bytes for a tab-separated joblog line: `%`, tab, `\r`, and `\n`
+/// are always escaped (so they can never be mistaken for a field separator or corrupt the line),
+/// and on Unix every other byte is round-tripped exactly via `OsStrExt`, rather than through
+/// `Path::display`'s lossy UTF-8 conversion. This matters because the whole point of the joblog
+/// is exact `--resume` matching: a lossy round-trip would silently fail to match a path containing
+/// invalid UTF-8, which is legal on Unix filesystems.
+fn escape_path(file: &Path) -> String {
+    let mut out = String::with_capacity(file.as_os_str().len());
+    for &byte in path_bytes(file) {
+        match byte {
+            b'%' | b'\t' | b'\r' | b'\n' => out.push_str(&format!("%{:02X}", byte)),
+            0x20..=0x7E => out.push(byte as char),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+/// Reverses [`escape_path`].
+fn unescape_path(escaped: &str) -> PathBuf {
+    let mut bytes = Vec::with_capacity(escaped.len());
+    let mut chars = escaped.bytes();
+    while let Some(byte) = chars.next() {
+        if byte == b'%' {
+            let hex: Option<u8> = chars
+                .next()
+                .zip(chars.next())
+                .and_then(|(hi, lo)| u8::from_str_radix(&format!("{}{}", hi as char, lo as char), 16).ok());
+            match hex {
+                Some(decoded) => bytes.push(decoded),
+                None => bytes.push(b'%'),
+            }
+        } else {
+            bytes.push(byte);
+        }
+    }
+    path_from_bytes(bytes)
+}
+
+#[cfg(unix)]
+fn path_bytes(file: &Path) -> &[u8] {
+    use std::os::unix::ffi::OsStrExt;
+    file.as_os_str().as_bytes()
+}
+
+#[cfg(not(unix))]
+fn path_bytes(file: &Path) -> &[u8] {
+    // Non-Unix `OsStr`s aren't a byte sequence we can recover exactly; best effort via UTF-8.
+    file.to_str().unwrap_or_default().as_bytes()
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(&bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Reads an existing joblog at `path` and returns the set of file paths already recorded with a
+/// `processed` outcome, so a `--resume` run can skip them.
+///
+/// Returns an empty set if `path` does not exist yet, since that simply means this is the first
+/// run.
+pub fn load_resumable_paths(path: &Path) -> Result<HashSet<PathBuf>> {
+    if !path.exists() {
+        return Ok(HashSet::new());
+    }
+
+    let file =
+        File::open(path).with_context(|| format!("Failed to open joblog at {}", path.display()))?;
+
+    let mut resumable = HashSet::new();
+    for line in BufReader::new(file).lines() {
+        let line =
+            line.with_context(|| format!("Failed to read joblog at {}", path.display()))?;
+        let mut fields = line.splitn(2, '\t');
+        let (Some(file_path), Some(rest)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        if rest.starts_with("processed\t") {
+            resumable.insert(unescape_path(file_path));
+        }
+    }
+
+    Ok(resumable)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_record_and_load_resumable_paths() {
+        let temp_dir = TempDir::new().unwrap();
+        let joblog_path = temp_dir.path().join("joblog.txt");
+
+        let joblog = JobLog::open(&joblog_path).unwrap();
+        let now = SystemTime::now();
+        joblog
+            .record(Path::new("a.txt"), JobLogOutcome::Processed, Some(0), now, now)
+            .unwrap();
+        joblog
+            .record(Path::new("b.txt"), JobLogOutcome::Errored, Some(1), now, now)
+            .unwrap();
+        drop(joblog);
+
+        let resumable = load_resumable_paths(&joblog_path).unwrap();
+
+        assert!(resumable.contains(&PathBuf::from("a.txt")));
+        assert!(!resumable.contains(&PathBuf::from("b.txt")));
+    }
+
+    #[test]
+    fn test_record_and_load_resumable_paths_with_embedded_tab() {
+        let temp_dir = TempDir::new().unwrap();
+        let joblog_path = temp_dir.path().join("joblog.txt");
+
+        let joblog = JobLog::open(&joblog_path).unwrap();
+        let now = SystemTime::now();
+        joblog
+            .record(
+                Path::new("weird\tname.txt"),
+                JobLogOutcome::Processed,
+                Some(0),
+                now,
+                now,
+            )
+            .unwrap();
+        drop(joblog);
+
+        let resumable = load_resumable_paths(&joblog_path).unwrap();
+
+        assert!(resumable.contains(&PathBuf::from("weird\tname.txt")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_record_and_load_resumable_paths_with_invalid_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let joblog_path = temp_dir.path().join("joblog.txt");
+        let weird_name = PathBuf::from(OsStr::from_bytes(b"not-\xffutf8.txt"));
+
+        let joblog = JobLog::open(&joblog_path).unwrap();
+        let now = SystemTime::now();
+        joblog
+            .record(&weird_name, JobLogOutcome::Processed, Some(0), now, now)
+            .unwrap();
+        drop(joblog);
+
+        let resumable = load_resumable_paths(&joblog_path).unwrap();
+
+        assert!(resumable.contains(&weird_name));
+    }
+
+    #[test]
+    fn test_load_resumable_paths_missing_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let joblog_path = temp_dir.path().join("does-not-exist.txt");
+
+        let resumable = load_resumable_paths(&joblog_path).unwrap();
+
+        assert!(resumable.is_empty());
+    }
+}
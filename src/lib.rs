@@ -1,13 +1,60 @@
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, Result};
+use ignore::{WalkBuilder, WalkState};
 use rayon::prelude::*;
-use std::ffi::OsStr;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io::Read;
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
 use std::path::{Path, PathBuf};
-use std::process::Command;
-use walkdir::WalkDir;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime};
+
+/// How long to wait between polls of a running child while checking `should_cancel`.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long a cancelled process group is given to exit after SIGTERM before SIGKILL follows.
+const CANCEL_GRACE_PERIOD: Duration = Duration::from_secs(5);
 
 mod context;
 pub use context::ProcessingContext;
 
+mod job_log;
+pub use job_log::{load_resumable_paths, JobLog, JobLogOutcome};
+
+mod path_filter;
+pub use path_filter::PathFilter;
+
+/// How `parallelize_chunk` should invoke work for each file.
+#[derive(Debug, Clone, Copy)]
+pub enum Invocation<'a> {
+    /// Run a fixed executable, appending the file path as the sole trailing argument.
+    Script(&'a Path),
+    /// Run a GNU parallel-style command template, expanding `{}`, `{.}`, `{/}`, `{//}`, and
+    /// `{/.}` placeholders for each file before invoking it.
+    Command(&'a str),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TaskOutcome {
+    Processed,
+    Errored,
+    Cancelled,
+}
+
+impl From<TaskOutcome> for JobLogOutcome {
+    fn from(outcome: TaskOutcome) -> Self {
+        match outcome {
+            TaskOutcome::Processed => JobLogOutcome::Processed,
+            TaskOutcome::Errored => JobLogOutcome::Errored,
+            TaskOutcome::Cancelled => JobLogOutcome::Cancelled,
+        }
+    }
+}
+
 /// Executes a command in parallel for a given chunk of file paths.
 ///
 /// This function processes a chunk of file paths in parallel, executing the specified command
@@ -16,9 +63,11 @@ pub use context::ProcessingContext;
 /// # Arguments
 ///
 /// * `chunk` - A slice of `PathBuf` representing the files to be processed.
-/// * `script` - An `Option<&Path>` containing the path to the script to be executed for each file.
-///              If `None`, the function will only log the file names without executing a script.
+/// * `invocation` - An `Option<Invocation>` describing how to run work for each file.
+///                  If `None`, the function will only log the file names without executing anything.
 /// * `should_cancel` - A closure that returns a boolean indicating whether the operation should be cancelled.
+/// * `job_log` - An optional [`JobLog`] to append a line to for every file once it finishes,
+///               whatever the outcome, so an interrupted run can be resumed with `--resume`.
 ///
 /// # Returns
 ///
@@ -29,6 +78,12 @@ pub use context::ProcessingContext;
 /// - The number of files that encountered errors during processing
 /// - The number of files that were cancelled due to the `should_cancel` condition
 ///
+/// Each invocation is spawned into its own process group (via `setpgid` on Unix), so when
+/// `should_cancel` flips mid-run the whole subtree - not just the direct child -
+/// can be torn down: a `SIGTERM` is sent to the group first, and if it hasn't exited within
+/// [`CANCEL_GRACE_PERIOD`] a `SIGKILL` follows. This keeps long-running scripts that spawn their
+/// own children from surviving cancellation as orphans.
+///
 /// # Errors
 ///
 /// This function may return an error if there are issues executing the script for any file
@@ -39,68 +94,91 @@ pub use context::ProcessingContext;
 /// use std::path::{Path, PathBuf};
 /// use std::sync::Arc;
 /// use std::sync::atomic::AtomicBool;
+/// use pfp::{parallelize_chunk, Invocation};
 ///
 /// let chunk = vec![PathBuf::from("file1.txt"), PathBuf::from("file2.txt")];
-/// let script = Some(Path::new("/usr/local/bin/process_file.sh"));
+/// let invocation = Some(Invocation::Script(Path::new("/usr/local/bin/process_file.sh")));
 /// let term_flag = Arc::new(AtomicBool::new(false));
 /// let should_cancel = || term_flag.load(std::sync::atomic::Ordering::Relaxed);
 ///
-/// let (processed, errored, cancelled) = parallelize_chunk(&chunk, script, should_cancel)
+/// let (processed, errored, cancelled) = parallelize_chunk(&chunk, invocation, should_cancel, None)
 ///     .expect("Failed to process chunk");
 /// println!("Processed: {}, Errored: {}, Cancelled: {}", processed, errored, cancelled);
 /// ```
+#[derive(Debug, Clone, Copy)]
+enum TaskOutcome {
+    Processed,
+    Errored,
+    Cancelled,
+}
+
+impl From<TaskOutcome> for JobLogOutcome {
+    fn from(outcome: TaskOutcome) -> Self {
+        match outcome {
+            TaskOutcome::Processed => JobLogOutcome::Processed,
+            TaskOutcome::Errored => JobLogOutcome::Errored,
+            TaskOutcome::Cancelled => JobLogOutcome::Cancelled,
+        }
+    }
+}
+
 pub fn parallelize_chunk<F>(
     chunk: &[PathBuf],
-    script: Option<&Path>,
+    invocation: Option<Invocation>,
     should_cancel: F,
+    job_log: Option<&JobLog>,
 ) -> Result<(usize, usize, usize)>
 where
     F: Fn() -> bool + Send + Sync,
 {
-    #[derive(Debug)]
-    enum TaskOutcome {
-        Processed,
-        Errored,
-        Cancelled,
-    }
-
     let results: Vec<TaskOutcome> = chunk
         .par_iter()
         .map(|file| -> TaskOutcome {
-            match script {
+            let started_at = SystemTime::now();
+
+            let (outcome, exit_status) = match invocation {
                 _ if should_cancel() => {
                     log::info!("Cancelling task for file: {}", file.display());
-                    TaskOutcome::Cancelled
+                    (TaskOutcome::Cancelled, None)
                 }
-                Some(script_path) => {
-                    match Command::new(script_path)
-                        .arg(file)
-                        .output()
-                        .with_context(|| {
-                            format!("Failed to execute script for file: {}", file.display())
-                        }) {
-                        Ok(output) if output.status.success() => {
-                            log::debug!("Processed file: {}", file.display());
-                            log::debug!("stdout: {}", String::from_utf8_lossy(&output.stdout));
-                            TaskOutcome::Processed
-                        }
-                        Ok(output) => {
-                            log::error!("Script failed for file: {}", file.display());
-                            log::error!("stderr: {}", String::from_utf8_lossy(&output.stderr));
-                            TaskOutcome::Errored
+                Some(Invocation::Script(script_path)) => {
+                    let mut command = Command::new(script_path);
+                    command.arg(file);
+                    run_with_cancellation(command, file, &should_cancel)
+                }
+                Some(Invocation::Command(template)) => {
+                    let args = expand_template(file, template);
+                    match args.split_first() {
+                        Some((cmd, rest)) => {
+                            let mut command = Command::new(cmd);
+                            command.args(rest);
+                            run_with_cancellation(command, file, &should_cancel)
                         }
-                        Err(e) => {
-                            log::error!("Failed to execute script for file: {}", file.display());
-                            log::error!("Error: {}", e);
-                            TaskOutcome::Errored
+                        None => {
+                            log::error!(
+                                "Command template expanded to no arguments for file: {}",
+                                file.display()
+                            );
+                            (TaskOutcome::Errored, None)
                         }
                     }
                 }
                 None => {
                     log::info!("Would process file: {}", file.display());
-                    TaskOutcome::Processed
+                    (TaskOutcome::Processed, None)
+                }
+            };
+
+            if let Some(job_log) = job_log {
+                let ended_at = SystemTime::now();
+                if let Err(e) =
+                    job_log.record(file, outcome.into(), exit_status, started_at, ended_at)
+                {
+                    log::error!("Failed to write joblog entry for {}: {}", file.display(), e);
                 }
             }
+
+            outcome
         })
         .collect();
 
@@ -120,6 +198,150 @@ where
     Ok((processed, errored, cancelled))
 }
 
+/// Spawns `command` into its own process group and polls both its completion and
+/// `should_cancel` instead of blocking unconditionally on the child, so an in-flight process
+/// (and anything it has spawned) can be torn down promptly when cancellation is requested.
+///
+/// stdout/stderr are drained on dedicated threads as soon as the child is spawned, rather than
+/// read back after it exits: the OS pipe buffer is only a few tens of KB, so a long-running
+/// process (exactly the transcoding-style use case this is built for) that writes more than that
+/// before finishing would otherwise block on `write()` forever, with nothing reading the pipe
+/// while the parent sits in the poll loop below.
+fn run_with_cancellation<F>(
+    mut command: Command,
+    file: &Path,
+    should_cancel: &F,
+) -> (TaskOutcome, Option<i32>)
+where
+    F: Fn() -> bool,
+{
+    #[cfg(unix)]
+    command.process_group(0);
+
+    command.stdout(Stdio::piped()).stderr(Stdio::piped());
+
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            log::error!("Failed to spawn process for file: {}", file.display());
+            log::error!("Error: {}", e);
+            return (TaskOutcome::Errored, None);
+        }
+    };
+
+    let stdout_handle = child.stdout.take().map(spawn_pipe_reader);
+    let stderr_handle = child.stderr.take().map(spawn_pipe_reader);
+
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                return finish_child(status, file, stdout_handle, stderr_handle)
+            }
+            Ok(None) => {}
+            Err(e) => {
+                log::error!("Failed to poll process for file: {}", file.display());
+                log::error!("Error: {}", e);
+                return (TaskOutcome::Errored, None);
+            }
+        }
+
+        if should_cancel() {
+            log::info!(
+                "Cancelling in-flight process group for file: {}",
+                file.display()
+            );
+            cancel_process_group(&mut child, file);
+            join_pipe_reader(stdout_handle);
+            join_pipe_reader(stderr_handle);
+            return (TaskOutcome::Cancelled, None);
+        }
+
+        std::thread::sleep(CANCEL_POLL_INTERVAL);
+    }
+}
+
+/// Spawns a thread that reads `pipe` to completion, so the child never blocks on a full pipe
+/// buffer while the caller is busy polling `try_wait`/`should_cancel`.
+fn spawn_pipe_reader<R: Read + Send + 'static>(mut pipe: R) -> JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut buf = String::new();
+        let _ = pipe.read_to_string(&mut buf);
+        buf
+    })
+}
+
+/// Waits for a pipe-draining thread to finish, discarding its output.
+fn join_pipe_reader(handle: Option<JoinHandle<String>>) {
+    if let Some(handle) = handle {
+        let _ = handle.join();
+    }
+}
+
+/// Joins the stdout/stderr-draining threads and classifies a finished child's outcome.
+fn finish_child(
+    status: ExitStatus,
+    file: &Path,
+    stdout_handle: Option<JoinHandle<String>>,
+    stderr_handle: Option<JoinHandle<String>>,
+) -> (TaskOutcome, Option<i32>) {
+    let stdout = stdout_handle
+        .and_then(|handle| handle.join().ok())
+        .unwrap_or_default();
+    let stderr = stderr_handle
+        .and_then(|handle| handle.join().ok())
+        .unwrap_or_default();
+
+    let outcome = if status.success() {
+        log::debug!("Processed file: {}", file.display());
+        log::debug!("stdout: {}", stdout);
+        TaskOutcome::Processed
+    } else {
+        log::error!("Process failed for file: {}", file.display());
+        log::error!("stderr: {}", stderr);
+        TaskOutcome::Errored
+    };
+
+    (outcome, status.code())
+}
+
+/// Sends `SIGTERM` to `child`'s whole process group, escalating to `SIGKILL` if it hasn't
+/// exited within [`CANCEL_GRACE_PERIOD`].
+#[cfg(unix)]
+fn cancel_process_group(child: &mut Child, file: &Path) {
+    let pgid = child.id() as libc::pid_t;
+    unsafe {
+        libc::kill(-pgid, libc::SIGTERM);
+    }
+
+    let deadline = Instant::now() + CANCEL_GRACE_PERIOD;
+    while Instant::now() < deadline {
+        if matches!(child.try_wait(), Ok(Some(_))) {
+            return;
+        }
+        std::thread::sleep(CANCEL_POLL_INTERVAL);
+    }
+
+    log::warn!(
+        "Process group for {} did not exit after SIGTERM, sending SIGKILL",
+        file.display()
+    );
+    unsafe {
+        libc::kill(-pgid, libc::SIGKILL);
+    }
+    let _ = child.wait();
+}
+
+/// Non-Unix fallback: kill only the direct child, since process groups are a Unix concept here.
+#[cfg(not(unix))]
+fn cancel_process_group(child: &mut Child, file: &Path) {
+    log::warn!(
+        "Process group cancellation is only supported on Unix; killing {} directly",
+        file.display()
+    );
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
 #[cfg(test)]
 mod parallelize_chunk_tests {
     use super::*;
@@ -148,7 +370,7 @@ mod parallelize_chunk_tests {
         let should_cancel = || term_flag.load(Ordering::Relaxed);
 
         let (processed, errored, cancelled) =
-            parallelize_chunk(&files, None, should_cancel).unwrap();
+            parallelize_chunk(&files, None, should_cancel, None).unwrap();
 
         assert_eq!(processed, 5);
         assert_eq!(errored, 0);
@@ -163,7 +385,7 @@ mod parallelize_chunk_tests {
         let should_cancel = || term_flag.load(Ordering::Relaxed);
 
         let (processed, errored, cancelled) =
-            parallelize_chunk(&files, None, should_cancel).unwrap();
+            parallelize_chunk(&files, None, should_cancel, None).unwrap();
 
         assert_eq!(processed, 0);
         assert_eq!(errored, 0);
@@ -193,8 +415,34 @@ mod parallelize_chunk_tests {
         let term_flag = Arc::new(AtomicBool::new(false));
         let should_cancel = || term_flag.load(Ordering::Relaxed);
 
-        let (processed, errored, cancelled) =
-            parallelize_chunk(&files, Some(&script_path), should_cancel).unwrap();
+        let (processed, errored, cancelled) = parallelize_chunk(
+            &files,
+            Some(Invocation::Script(&script_path)),
+            should_cancel,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(processed, 3);
+        assert_eq!(errored, 0);
+        assert_eq!(cancelled, 0);
+    }
+
+    #[test]
+    fn test_parallelize_chunk_with_command_template() {
+        let temp_dir = TempDir::new().unwrap();
+        let files = create_test_files(temp_dir.path(), 3);
+
+        let term_flag = Arc::new(AtomicBool::new(false));
+        let should_cancel = || term_flag.load(Ordering::Relaxed);
+
+        let (processed, errored, cancelled) = parallelize_chunk(
+            &files,
+            Some(Invocation::Command("echo {}")),
+            should_cancel,
+            None,
+        )
+        .unwrap();
 
         assert_eq!(processed, 3);
         assert_eq!(errored, 0);
@@ -202,89 +450,318 @@ mod parallelize_chunk_tests {
     }
 }
 
-/// Recursively retrieves files from a given directory, optionally filtering by file extensions.
+/// Expands GNU parallel-style placeholder tokens in `template` for a single file path.
 ///
-/// This function traverses the directory structure starting from the provided `input_path`,
-/// collecting file paths that match the specified criteria.
+/// Supported tokens:
+/// - `{}` - the full path, e.g. `dir/file.txt`
+/// - `{.}` - the full path with its extension stripped, e.g. `dir/file`
+/// - `{/}` - the basename, e.g. `file.txt`
+/// - `{//}` - the parent directory, e.g. `dir`
+/// - `{/.}` - the basename with its extension stripped, e.g. `file`
 ///
-/// # Arguments
+/// `template` is first tokenized the way a shell would (so quoted arguments stay together),
+/// then each token has its placeholders expanded. A token may contain a placeholder more than
+/// once; every occurrence is expanded.
 ///
-/// * `input_path` - A reference to the `Path` representing the starting directory.
-/// * `extensions` - An optional `Vec<&OsStr>` containing file extensions to filter by.
-///                  If `None`, all files are included.
+/// # Example
+/// ```
+/// use std::path::Path;
+/// use std::ffi::OsString;
 ///
-/// # Returns
+/// let args = pfp::expand_template(Path::new("dir/file.txt"), "convert {} {.}.png");
+/// assert_eq!(
+///     args,
+///     vec![
+///         OsString::from("convert"),
+///         OsString::from("dir/file.txt"),
+///         OsString::from("dir/file.png"),
+///     ]
+/// );
+/// ```
+pub fn expand_template(file: &Path, template: &str) -> Vec<OsString> {
+    shell_words::split(template)
+        .unwrap_or_else(|_| vec![template.to_string()])
+        .into_iter()
+        .map(|token| OsString::from(expand_template_token(file, &token)))
+        .collect()
+}
+
+fn expand_template_token(file: &Path, token: &str) -> String {
+    let full = file.to_string_lossy().into_owned();
+    let stem_path = file.with_extension("");
+    let stem_full = stem_path.to_string_lossy().into_owned();
+    let parent = file
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|| ".".to_string());
+    let basename = file
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| full.clone());
+    let basename_stem = file
+        .file_stem()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| basename.clone());
+
+    // Scan `token` once, left to right, rather than chaining `String::replace` calls: chaining
+    // would re-scan already-substituted text, so a replacement containing an accidental
+    // `{...}`-shaped substring (legal in Unix filenames) could get expanded a second time.
+    let placeholders: [(&str, &str); 5] = [
+        ("{//}", &parent),
+        ("{/.}", &basename_stem),
+        ("{/}", &basename),
+        ("{.}", &stem_full),
+        ("{}", &full),
+    ];
+
+    let mut out = String::with_capacity(token.len());
+    let mut rest = token;
+    'scan: while !rest.is_empty() {
+        for (placeholder, replacement) in &placeholders {
+            if let Some(remainder) = rest.strip_prefix(placeholder) {
+                out.push_str(replacement);
+                rest = remainder;
+                continue 'scan;
+            }
+        }
+        let mut chars = rest.chars();
+        out.push(chars.next().expect("rest is non-empty"));
+        rest = chars.as_str();
+    }
+    out
+}
+
+#[cfg(test)]
+mod expand_template_tests {
+    use super::*;
+
+    #[test]
+    fn test_full_path_token() {
+        let args = expand_template(Path::new("dir/file.txt"), "{}");
+        assert_eq!(args, vec![OsString::from("dir/file.txt")]);
+    }
+
+    #[test]
+    fn test_strip_extension_token() {
+        let args = expand_template(Path::new("dir/file.txt"), "{.}");
+        assert_eq!(args, vec![OsString::from("dir/file")]);
+    }
+
+    #[test]
+    fn test_basename_token() {
+        let args = expand_template(Path::new("dir/file.txt"), "{/}");
+        assert_eq!(args, vec![OsString::from("file.txt")]);
+    }
+
+    #[test]
+    fn test_parent_dir_token() {
+        let args = expand_template(Path::new("dir/file.txt"), "{//}");
+        assert_eq!(args, vec![OsString::from("dir")]);
+    }
+
+    #[test]
+    fn test_basename_without_extension_token() {
+        let args = expand_template(Path::new("dir/file.txt"), "{/.}");
+        assert_eq!(args, vec![OsString::from("file")]);
+    }
+
+    #[test]
+    fn test_multiple_occurrences_of_same_token() {
+        let args = expand_template(Path::new("dir/file.txt"), "cp {} {}.bak");
+        assert_eq!(
+            args,
+            vec![
+                OsString::from("cp"),
+                OsString::from("dir/file.txt"),
+                OsString::from("dir/file.txt.bak"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_full_template_with_mixed_tokens() {
+        let args = expand_template(Path::new("dir/file.txt"), "convert {} {.}.png");
+        assert_eq!(
+            args,
+            vec![
+                OsString::from("convert"),
+                OsString::from("dir/file.txt"),
+                OsString::from("dir/file.png"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_parent_directory() {
+        let args = expand_template(Path::new("file.txt"), "{//}/{/}");
+        assert_eq!(args, vec![OsString::from("./file.txt")]);
+    }
+
+    #[test]
+    fn test_substituted_text_is_not_rescanned_for_other_tokens() {
+        // The basename itself contains a literal `{.}`-shaped substring; expanding `{/}` must
+        // not cause that substring to be mistaken for the `{.}` placeholder on a later pass.
+        let args = expand_template(Path::new("dir/a{.}b.txt"), "{/}");
+        assert_eq!(args, vec![OsString::from("a{.}b.txt")]);
+    }
+}
+
+/// Builds the shared `ignore`-based parallel walker used by [`spawn_file_stream`].
+fn build_walker(input_path: &Path, no_ignore: bool, hidden: bool) -> ignore::WalkParallel {
+    WalkBuilder::new(input_path)
+        .git_ignore(!no_ignore)
+        .git_exclude(!no_ignore)
+        .ignore(!no_ignore)
+        .hidden(!hidden)
+        .build_parallel()
+}
+
+/// Bound of the channel connecting the walking producer thread to chunk dispatch. Keeping this
+/// small caps how far discovery can run ahead of processing, bounding memory on huge trees.
+const STREAM_CHANNEL_CAPACITY: usize = 256;
+
+/// Walks `input_path` on a background producer thread, streaming matching file paths into the
+/// returned channel as they're discovered instead of collecting them all up front.
 ///
-/// Returns a `Result` containing a `Vec<PathBuf>` of matching file paths.
+/// This lets callers start dispatching chunks to [`parallelize_chunk`] while the walk is still in
+/// progress, which bounds memory use and dramatically lowers time-to-first-result on directories
+/// with huge numbers of files, at the cost of not knowing the total file count ahead of time.
+/// `term` is polled between entries so cancellation stops the walk promptly instead of running it
+/// to completion.
 ///
 /// # Errors
 ///
-/// This function may return an error if there are issues with file system operations
-/// or directory traversal.
-pub fn get_files(input_path: &Path, extensions: &Option<Vec<&OsStr>>) -> Result<Vec<PathBuf>> {
-    let should_include = |file_path: &Path| -> bool {
-        if let Some(exts) = extensions {
-            file_path
-                .extension()
-                .map(|ext| exts.contains(&ext))
-                .unwrap_or(false)
-        } else {
-            true
-        }
-    };
-
-    // Check if the input path exists before walking
+/// Returns an error immediately if `input_path` does not exist. Errors encountered while walking
+/// are logged and skipped rather than failing the whole stream, since by that point the caller
+/// may already be processing files from earlier in the walk.
+pub fn spawn_file_stream(
+    input_path: &Path,
+    filter: &PathFilter,
+    no_ignore: bool,
+    hidden: bool,
+    term: Arc<AtomicBool>,
+) -> Result<crossbeam_channel::Receiver<PathBuf>> {
     if !input_path.exists() {
         return Err(anyhow!("Input path does not exist"));
     }
 
-    // TODO: parallelize this with rayon!
-
-    // For loop isn't idiomatic Rust, but it was a start
-    //for entry in WalkDir::new(input_path).into_iter() {
-    //    let entry =
-    //        entry.with_context(|| format!("Failed to read entry in {}", input_path.display()))?;
-    //    if entry.file_type().is_file() && should_include(entry.path()) {
-    //        files.push(entry.path().to_path_buf());
-    //    }
-    //}
-
-    // using map and filter to collect files and return early if an error occurs
-    //let files = WalkDir::new(input_path)
-    //    .into_iter()
-    //    .map(|entry| {
-    //        entry.with_context(|| format!("Failed to read entry in {}", input_path.display()))
-    //    })
-    //    .filter(|entry| match entry {
-    //        Ok(e) => e.file_type().is_file() && should_include(e.path()),
-    //        Err(_) => true,
-    //    })
-    //    .map(|entry| entry.map(|e| e.path().to_path_buf()))
-    //    .collect::<Result<Vec<PathBuf>>>()?;
-
-    // Even better is using filter_map to handle both Ok and Err cases
-    // See https://doc.rust-lang.org/rust-by-example/error/iter_result.html#fail-the-entire-operation-with-collect
-    let files = WalkDir::new(input_path)
-        .into_iter()
-        .filter_map(|entry| {
-            match entry.with_context(|| format!("Failed to read entry in {}", input_path.display()))
-            {
-                Ok(e) if e.file_type().is_file() && should_include(e.path()) => {
-                    Some(Ok(e.path().to_path_buf()))
+    let (tx, rx) = crossbeam_channel::bounded::<PathBuf>(STREAM_CHANNEL_CAPACITY);
+
+    let walker = build_walker(input_path, no_ignore, hidden);
+    let filter = filter.clone();
+    let input_path_display = input_path.display().to_string();
+
+    std::thread::spawn(move || {
+        walker.run(|| {
+            let tx = tx.clone();
+            let term = term.clone();
+            let filter = filter.clone();
+            let input_path_display = input_path_display.clone();
+            Box::new(move |entry| {
+                if term.load(Ordering::Relaxed) {
+                    return WalkState::Quit;
                 }
-                Ok(_) => None,
-                Err(e) => Some(Err(e)),
-            }
-        })
-        .collect::<Result<Vec<PathBuf>>>()?;
 
-    Ok(files)
+                match entry {
+                    Ok(entry) => {
+                        let is_file = entry.file_type().map(|ft| ft.is_file()).unwrap_or(false);
+                        if is_file && filter.matches(entry.path()) {
+                            if tx.send(entry.path().to_path_buf()).is_err() {
+                                // Receiver dropped; the consumer is gone so stop walking.
+                                return WalkState::Quit;
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        log::error!("Failed to read entry in {}: {}", input_path_display, e);
+                    }
+                }
+                WalkState::Continue
+            })
+        });
+    });
+
+    Ok(rx)
+}
+
+/// Removes and returns the paths in `pending` whose most recent event is at least
+/// `quiet_window` old, i.e. no further event has arrived for them within that window.
+///
+/// This is the debouncing core of `--watch` mode: callers insert a path/`Instant` into `pending`
+/// each time a filesystem event for that path is observed, then periodically call this function
+/// to drain the paths that have gone quiet and are safe to hand off to [`parallelize_chunk`].
+/// Paths that are still within their quiet window are left in `pending` for the next call.
+///
+/// # Example
+/// ```
+/// use std::collections::HashMap;
+/// use std::path::PathBuf;
+/// use std::time::{Duration, Instant};
+///
+/// let mut pending: HashMap<PathBuf, Instant> = HashMap::new();
+/// pending.insert(PathBuf::from("file.txt"), Instant::now() - Duration::from_millis(500));
+///
+/// let ready = pfp::drain_debounced_paths(&mut pending, Duration::from_millis(200));
+/// assert_eq!(ready, vec![PathBuf::from("file.txt")]);
+/// assert!(pending.is_empty());
+/// ```
+pub fn drain_debounced_paths(
+    pending: &mut HashMap<PathBuf, Instant>,
+    quiet_window: Duration,
+) -> Vec<PathBuf> {
+    let now = Instant::now();
+    let ready: Vec<PathBuf> = pending
+        .iter()
+        .filter(|(_, &last_event)| now.duration_since(last_event) >= quiet_window)
+        .map(|(path, _)| path.clone())
+        .collect();
+
+    for path in &ready {
+        pending.remove(path);
+    }
+
+    ready
 }
 
 #[cfg(test)]
-mod tests {
+mod debounce_tests {
     use super::*;
+
+    #[test]
+    fn test_drain_debounced_paths_releases_quiet_paths() {
+        let mut pending = HashMap::new();
+        pending.insert(
+            PathBuf::from("quiet.txt"),
+            Instant::now() - Duration::from_millis(500),
+        );
+        pending.insert(PathBuf::from("fresh.txt"), Instant::now());
+
+        let ready = drain_debounced_paths(&mut pending, Duration::from_millis(200));
+
+        assert_eq!(ready, vec![PathBuf::from("quiet.txt")]);
+        assert_eq!(pending.len(), 1);
+        assert!(pending.contains_key(&PathBuf::from("fresh.txt")));
+    }
+
+    #[test]
+    fn test_drain_debounced_paths_empty_when_nothing_quiet() {
+        let mut pending = HashMap::new();
+        pending.insert(PathBuf::from("fresh.txt"), Instant::now());
+
+        let ready = drain_debounced_paths(&mut pending, Duration::from_millis(200));
+
+        assert!(ready.is_empty());
+        assert_eq!(pending.len(), 1);
+    }
+}
+
+#[cfg(test)]
+mod spawn_file_stream_tests {
+    use super::*;
+    use std::ffi::OsStr;
     use std::fs::{self, File};
+    use std::sync::atomic::AtomicBool;
     use tempfile::TempDir;
 
     fn create_test_directory() -> TempDir {
@@ -302,12 +779,29 @@ mod tests {
         temp_dir
     }
 
+    fn collect_stream(
+        input_path: &Path,
+        filter: &PathFilter,
+        no_ignore: bool,
+        hidden: bool,
+    ) -> Result<Vec<PathBuf>> {
+        let rx = spawn_file_stream(
+            input_path,
+            filter,
+            no_ignore,
+            hidden,
+            Arc::new(AtomicBool::new(false)),
+        )?;
+        Ok(rx.into_iter().collect())
+    }
+
     #[test]
-    fn test_get_files3_with_extensions() {
+    fn test_spawn_file_stream_with_extensions() {
         let temp_dir = create_test_directory();
         let extensions = Some(vec![OsStr::new("txt"), OsStr::new("jpg")]);
+        let filter = PathFilter::new(&extensions, &[], &[]).unwrap();
 
-        let files = get_files(temp_dir.path(), &extensions).unwrap();
+        let files = collect_stream(temp_dir.path(), &filter, false, false).unwrap();
 
         assert_eq!(files.len(), 4);
         assert!(files.iter().any(|f| f.file_name().unwrap() == "file1.txt"));
@@ -317,35 +811,53 @@ mod tests {
     }
 
     #[test]
-    fn test_get_files3_without_extensions() {
+    fn test_spawn_file_stream_without_extensions() {
         let temp_dir = create_test_directory();
-        let extensions = None;
 
-        let files = get_files(temp_dir.path(), &extensions).unwrap();
+        let files = collect_stream(temp_dir.path(), &PathFilter::any(), false, false).unwrap();
 
         assert_eq!(files.len(), 5);
     }
 
     #[test]
-    fn test_get_files3_empty_directory() {
+    fn test_spawn_file_stream_with_include_glob() {
+        let temp_dir = create_test_directory();
+        let filter = PathFilter::new(&None, &["**/*.jpg".to_string()], &[]).unwrap();
+
+        let files = collect_stream(temp_dir.path(), &filter, false, false).unwrap();
+
+        assert_eq!(files.len(), 2);
+        assert!(files.iter().any(|f| f.file_name().unwrap() == "file2.jpg"));
+        assert!(files.iter().any(|f| f.file_name().unwrap() == "file5.jpg"));
+    }
+
+    #[test]
+    fn test_spawn_file_stream_with_exclude_glob() {
+        let temp_dir = create_test_directory();
+        let filter = PathFilter::new(&None, &[], &["**/subdir/**".to_string()]).unwrap();
+
+        let files = collect_stream(temp_dir.path(), &filter, false, false).unwrap();
+
+        assert_eq!(files.len(), 3);
+        assert!(!files.iter().any(|f| f.file_name().unwrap() == "file4.txt"));
+        assert!(!files.iter().any(|f| f.file_name().unwrap() == "file5.jpg"));
+    }
+
+    #[test]
+    fn test_spawn_file_stream_empty_directory() {
         let temp_dir = TempDir::new().unwrap();
-        let extensions = None;
 
-        let files = get_files(temp_dir.path(), &extensions).unwrap();
+        let files = collect_stream(temp_dir.path(), &PathFilter::any(), false, false).unwrap();
 
         assert!(files.is_empty());
     }
 
     #[test]
-    fn test_get_files3_non_existent_directory() {
+    fn test_spawn_file_stream_non_existent_directory() {
         let non_existent_path = Path::new("/this/path/does/not/exist");
-        let extensions = None;
 
-        let result = get_files(non_existent_path, &extensions);
+        let result = collect_stream(non_existent_path, &PathFilter::any(), false, false);
 
         assert!(result.is_err());
     }
-
-    // Additional tests can be added here, such as testing for permission errors,
-    // or more complex directory structures.
 }
@@ -1,12 +1,15 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use clap::Parser;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use pfp::ProcessingContext;
+use std::collections::HashMap;
 use std::ffi::OsStr;
 use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
+use std::sync::mpsc::{channel, RecvTimeoutError};
 use std::sync::Arc;
 use std::thread::sleep;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use pfp::term_if_signal_rcvd;
 
@@ -18,15 +21,44 @@ struct Opt {
     debug: bool,
 
     /// Process files in input path continuously
-    #[clap(long)]
+    #[clap(long, conflicts_with = "watch")]
     daemon: bool,
 
+    /// Watch input path for filesystem events instead of polling on an interval.
+    /// Changed files are debounced for --watch-debounce-ms before being processed.
+    #[clap(long, conflicts_with = "daemon")]
+    watch: bool,
+
+    /// Milliseconds of quiet time to wait after the last event for a file before processing it.
+    /// Only used if --watch is specified.
+    #[clap(long, default_value = "200")]
+    watch_debounce_ms: u64,
+
+    /// Don't respect .gitignore, .ignore, and repository exclude files while walking input_path.
+    #[clap(long)]
+    no_ignore: bool,
+
+    /// Include hidden files and directories while walking input_path.
+    #[clap(long)]
+    hidden: bool,
+
     /// List of extensions delimited by commas. Only files ending in these extensions
     /// will be processed. E.g. -e "mp4,flv"
-    /// If this option is not provided then all files under the input_path will be processed
+    /// If this option is not provided then all files under the input_path will be processed.
+    /// Sugar for `--include '**/*.<ext>'`; can be combined with --include/--exclude.
     #[clap(short, long)]
     extensions: Option<String>,
 
+    /// Glob pattern of files to include, e.g. '**/*.{mp4,mkv}'. Repeatable. A file must match at
+    /// least one include glob (or --extensions) to be processed, unless neither is given.
+    #[clap(long = "include")]
+    include: Vec<String>,
+
+    /// Glob pattern of files to exclude, e.g. '**/sample/**'. Repeatable. Excludes always take
+    /// precedence over --include/--extensions.
+    #[clap(long = "exclude")]
+    exclude: Vec<String>,
+
     /// Number of things to try to do in parallel at one time.
     /// This is the number inputs that will be fed to Rayon. The actual number of parallel jobs per chunk is limited
     /// by job_slots.
@@ -43,42 +75,194 @@ struct Opt {
     sleep_time: u64,
 
     /// Shell script to run in parallel
-    #[clap(short, long)]
+    #[clap(short, long, conflicts_with = "command")]
     script: Option<PathBuf>,
 
+    /// GNU parallel-style command template to run in parallel, e.g. "convert {} {.}.png".
+    /// Supports the {}, {.}, {/}, {//}, and {/.} placeholder tokens in place of a --script file.
+    #[clap(long, conflicts_with = "script")]
+    command: Option<String>,
+
+    /// Path to a joblog recording one line per processed file (path, exit status, start/end
+    /// timestamps, and outcome). Relative paths are resolved against --tempdir. Required by
+    /// --resume.
+    #[clap(long)]
+    joblog: Option<PathBuf>,
+
+    /// Skip files already recorded as successfully processed in --joblog, to cheaply resume an
+    /// interrupted run instead of reprocessing everything. Requires --joblog.
+    #[clap(long)]
+    resume: bool,
+
+    /// Directory the joblog and any intermediate bookkeeping live in. Defaults to the system
+    /// temp dir.
+    #[clap(long)]
+    tempdir: Option<PathBuf>,
+
     /// Directory to read files from
     input_path: PathBuf,
 }
 
-fn process_files(context: &ProcessingContext) -> Result<()> {
-    let files = pfp::get_files(context.input_path, context.extensions)?;
+/// How long to keep buffering discovered files before flushing a partial chunk, so short runs
+/// that never fill a full chunk still get processed promptly.
+const STREAM_MAX_BUFFER_TIME: Duration = Duration::from_millis(100);
+
+fn process_files(context: &ProcessingContext, job_log: Option<&pfp::JobLog>) -> Result<()> {
+    let rx = pfp::spawn_file_stream(
+        context.input_path,
+        context.filter,
+        context.no_ignore,
+        context.hidden,
+        context.term.clone(),
+    )?;
 
     term_if_signal_rcvd!(context);
 
-    let (processed_files, errored_files, cancelled_files) = process_file_chunks(context, &files)?;
+    let (total_files, processed_files, errored_files, cancelled_files, skipped_files) =
+        dispatch_file_stream(context, rx, job_log)?;
 
-    log_processing_results(&files, processed_files, errored_files, cancelled_files);
+    log_processing_results(
+        total_files,
+        processed_files,
+        errored_files,
+        cancelled_files,
+        skipped_files,
+    );
 
     Ok(())
 }
 
+/// Drains a streaming walk into fixed-size chunks and dispatches each one to `parallelize_chunk`
+/// as soon as it fills, so processing overlaps discovery instead of waiting for the whole walk to
+/// finish. A partial chunk is flushed once its oldest file has been buffered for
+/// `STREAM_MAX_BUFFER_TIME` and no further file is waiting in the channel, mirroring fd's
+/// buffering/streaming receiver so short runs still batch nicely.
+fn dispatch_file_stream(
+    context: &ProcessingContext,
+    rx: crossbeam_channel::Receiver<PathBuf>,
+    job_log: Option<&pfp::JobLog>,
+) -> Result<(usize, usize, usize, usize, usize)> {
+    use crossbeam_channel::RecvTimeoutError;
+
+    let mut total_files = 0;
+    let mut processed_files = 0;
+    let mut errored_files = 0;
+    let mut cancelled_files = 0;
+    let mut skipped_files = 0;
+    let mut chunk_number = 0;
+
+    let mut buffer: Vec<PathBuf> = Vec::with_capacity(context.chunk_size);
+    let mut buffer_started_at: Option<Instant> = None;
+
+    loop {
+        term_if_signal_rcvd!(
+            context,
+            (
+                total_files,
+                processed_files,
+                errored_files,
+                cancelled_files,
+                skipped_files
+            )
+        );
+
+        let timeout = buffer_started_at
+            .map(|started| STREAM_MAX_BUFFER_TIME.saturating_sub(started.elapsed()))
+            .unwrap_or(STREAM_MAX_BUFFER_TIME);
+
+        let mut disconnected = false;
+        let should_flush = match rx.recv_timeout(timeout) {
+            Ok(path) => {
+                if context.resume_skip.contains(&path) {
+                    skipped_files += 1;
+                } else {
+                    if buffer.is_empty() {
+                        buffer_started_at = Some(Instant::now());
+                    }
+                    buffer.push(path);
+                }
+                buffer.len() >= context.chunk_size
+            }
+            Err(RecvTimeoutError::Timeout) => !buffer.is_empty(),
+            Err(RecvTimeoutError::Disconnected) => {
+                disconnected = true;
+                !buffer.is_empty()
+            }
+        };
+
+        if should_flush {
+            chunk_number += 1;
+            log::debug!("chunk {} ({}): START", chunk_number, buffer.len());
+
+            let should_cancel = || context.term_signal_rcvd();
+            let invocation = invocation_for(context);
+            let (processed, errored, cancelled) =
+                pfp::parallelize_chunk(&buffer, invocation, should_cancel, job_log)?;
+
+            total_files += buffer.len();
+            processed_files += processed;
+            errored_files += errored;
+            cancelled_files += cancelled;
+
+            log::debug!("chunk {} ({}): DONE", chunk_number, buffer.len());
+
+            buffer.clear();
+            buffer_started_at = None;
+        }
+
+        if disconnected {
+            break;
+        }
+    }
+
+    Ok((
+        total_files,
+        processed_files,
+        errored_files,
+        cancelled_files,
+        skipped_files,
+    ))
+}
+
+/// Resolves which `--script` or `--command` the user asked for into a single `Invocation`,
+/// since `ProcessingContext` keeps them as separate optional fields.
+fn invocation_for<'a>(context: &ProcessingContext<'a>) -> Option<pfp::Invocation<'a>> {
+    match (context.script, context.command) {
+        (Some(script), _) => Some(pfp::Invocation::Script(script)),
+        (None, Some(command)) => Some(pfp::Invocation::Command(command)),
+        (None, None) => None,
+    }
+}
+
 fn process_file_chunks(
     context: &ProcessingContext,
     files: &[PathBuf],
-) -> Result<(usize, usize, usize)> {
+    job_log: Option<&pfp::JobLog>,
+) -> Result<(usize, usize, usize, usize)> {
+    let original_count = files.len();
+    let files: Vec<PathBuf> = files
+        .iter()
+        .filter(|f| !context.resume_skip.contains(*f))
+        .cloned()
+        .collect();
+    let skipped_files = original_count - files.len();
     let total_chunks = (files.len() + context.chunk_size - 1) / context.chunk_size;
     let mut processed_files = 0;
     let mut errored_files = 0;
     let mut cancelled_files = 0;
 
     for (n, chunk) in files.chunks(context.chunk_size).enumerate() {
-        term_if_signal_rcvd!(context, (processed_files, errored_files, cancelled_files));
+        term_if_signal_rcvd!(
+            context,
+            (processed_files, errored_files, cancelled_files, skipped_files)
+        );
 
         log::debug!("chunk {}/{} ({}): START", n + 1, total_chunks, chunk.len());
 
         let should_cancel = || context.term_signal_rcvd();
+        let invocation = invocation_for(context);
         let (processed, errored, cancelled) =
-            pfp::parallelize_chunk(chunk, context.script, should_cancel)?;
+            pfp::parallelize_chunk(chunk, invocation, should_cancel, job_log)?;
 
         processed_files += processed;
         errored_files += errored;
@@ -87,19 +271,21 @@ fn process_file_chunks(
         log::debug!("chunk {}/{} ({}): DONE", n + 1, total_chunks, chunk.len());
     }
 
-    Ok((processed_files, errored_files, cancelled_files))
+    Ok((processed_files, errored_files, cancelled_files, skipped_files))
 }
 
 fn log_processing_results(
-    files: &[PathBuf],
+    total_files: usize,
     processed_files: usize,
     errored_files: usize,
     cancelled_files: usize,
+    skipped_files: usize,
 ) {
-    log::debug!("Total number of files {}", files.len());
+    log::debug!("Total number of files {}", total_files);
     log::debug!("Total number of processed files {}", processed_files);
     log::debug!("Total number of errored files {}", errored_files);
     log::debug!("Total number of cancelled files {}", cancelled_files);
+    log::debug!("Total number of skipped (resumed) files {}", skipped_files);
     log::info!("PFP: Finished processing all files in input-path.");
 }
 
@@ -108,10 +294,81 @@ fn sleep_daemon(sleep_time: u64) {
     sleep(Duration::from_secs(sleep_time));
 }
 
+/// Watch `input_path` for filesystem events and process files shortly after they go quiet,
+/// instead of re-scanning the whole tree on a fixed interval.
+///
+/// Incoming create/modify events are buffered in a map keyed by path with the timestamp of the
+/// last event seen for it. Once `watch_debounce_ms` has elapsed without a further event for a
+/// path, it is handed off to `parallelize_chunk`. This avoids processing partially-written files
+/// and collapses duplicate events (e.g. several writes to the same file) into a single run.
+fn run_watch(context: &ProcessingContext, job_log: Option<&pfp::JobLog>) -> Result<()> {
+    context.setup_signal_handling()?;
+    context.configure_thread_pool()?;
+
+    let (tx, rx) = channel::<notify::Result<Event>>();
+    let mut watcher: RecommendedWatcher = notify::recommended_watcher(tx)
+        .with_context(|| "Failed to create filesystem watcher")?;
+    watcher
+        .watch(context.input_path, RecursiveMode::Recursive)
+        .with_context(|| format!("Failed to watch {}", context.input_path.display()))?;
+
+    log::info!(
+        "PFP: watching {} for changes (debounce {}ms)...",
+        context.input_path.display(),
+        context.watch_debounce_ms
+    );
+
+    let quiet_window = Duration::from_millis(context.watch_debounce_ms);
+    let mut pending: HashMap<PathBuf, Instant> = HashMap::new();
+
+    loop {
+        term_if_signal_rcvd!(context);
+
+        match rx.recv_timeout(quiet_window) {
+            Ok(Ok(event)) => record_watch_event(context, &mut pending, event),
+            Ok(Err(e)) => log::error!("Watch error: {}", e),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(anyhow!("Watcher channel disconnected"));
+            }
+        }
+
+        let ready = pfp::drain_debounced_paths(&mut pending, quiet_window);
+        if !ready.is_empty() {
+            log::debug!("Dispatching {} debounced file(s)", ready.len());
+            let (processed, errored, cancelled, skipped) =
+                process_file_chunks(context, &ready, job_log)?;
+            log_processing_results(ready.len(), processed, errored, cancelled, skipped);
+        }
+    }
+}
+
+/// Records a single filesystem event's paths into `pending`, applying the same `PathFilter`
+/// (extensions and include/exclude globs) as the polling/daemon path so `--watch` only tracks files the user cares about.
+fn record_watch_event(
+    context: &ProcessingContext,
+    pending: &mut HashMap<PathBuf, Instant>,
+    event: Event,
+) {
+    if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+        return;
+    }
+
+    for path in event.paths {
+        if path.is_file() && context.filter.matches(&path) {
+            pending.insert(path, Instant::now());
+        }
+    }
+}
+
 /// Do the thing forever unless interrupted.
 /// Read all files in the input path and break them into chunks to execute in parallel
 /// Wait for each chunk to complete before processing the next chunk
-fn run(context: &ProcessingContext) -> Result<()> {
+fn run(context: &ProcessingContext, job_log: Option<&pfp::JobLog>) -> Result<()> {
+    if context.watch {
+        return run_watch(context, job_log);
+    }
+
     context.setup_signal_handling()?;
     context.configure_thread_pool()?;
 
@@ -120,7 +377,7 @@ fn run(context: &ProcessingContext) -> Result<()> {
 
         term_if_signal_rcvd!(context);
 
-        process_files(context)?;
+        process_files(context, job_log)?;
 
         if !context.daemon {
             log::info!("PFP: Not running as daemon, exiting...");
@@ -150,6 +407,24 @@ fn main() -> Result<()> {
         }
     }
 
+    if opt.resume && opt.joblog.is_none() {
+        return Err(anyhow!("--resume requires --joblog"));
+    }
+
+    let tempdir = opt.tempdir.clone().unwrap_or_else(std::env::temp_dir);
+    let joblog_path = opt
+        .joblog
+        .as_ref()
+        .map(|path| resolve_against(&tempdir, path));
+    let resume_skip = match (&joblog_path, opt.resume) {
+        (Some(path), true) => pfp::load_resumable_paths(path)?,
+        _ => std::collections::HashSet::new(),
+    };
+    let job_log = joblog_path
+        .as_ref()
+        .map(|path| pfp::JobLog::open(path))
+        .transpose()?;
+
     // Process the extensions input:
     // 1. Split the comma-separated string into individual extensions
     // 2. Trim whitespace from each extension
@@ -164,6 +439,7 @@ fn main() -> Result<()> {
             .map(OsStr::new)
             .collect::<Vec<&OsStr>>()
     });
+    let filter = pfp::PathFilter::new(&ext_vec, &opt.include, &opt.exclude)?;
 
     env_logger::builder()
         .target(env_logger::Target::Stdout)
@@ -174,16 +450,34 @@ fn main() -> Result<()> {
 
     let context = ProcessingContext {
         chunk_size: opt.chunk_size,
-        extensions: &ext_vec,
+        filter: &filter,
         input_path: &opt.input_path,
         job_slots: opt.job_slots,
         script: opt.script.as_deref(),
+        command: opt.command.as_deref(),
         term: Arc::new(AtomicBool::new(false)),
         sleep_time: opt.sleep_time,
         daemon: opt.daemon,
+        watch: opt.watch,
+        watch_debounce_ms: opt.watch_debounce_ms,
+        no_ignore: opt.no_ignore,
+        hidden: opt.hidden,
+        joblog: joblog_path.as_deref(),
+        tempdir: &tempdir,
+        resume_skip: &resume_skip,
     };
 
-    run(&context)?;
+    run(&context, job_log.as_ref())?;
 
     Ok(())
 }
+
+/// Resolves a user-provided `--joblog` path against `--tempdir` when it's relative, so joblogs
+/// live alongside other bookkeeping by default without forcing users to spell out the full path.
+fn resolve_against(tempdir: &std::path::Path, path: &std::path::Path) -> PathBuf {
+    if path.is_relative() {
+        tempdir.join(path)
+    } else {
+        path.to_path_buf()
+    }
+}
@@ -0,0 +1,142 @@
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::ffi::OsStr;
+use std::path::Path;
+
+/// Decides which files `spawn_file_stream`/`--watch` should process, combining the
+/// legacy comma-separated `-e/--extensions` list with richer `--include`/`--exclude` globs
+/// (backed by the `globset` crate) into a single predicate.
+///
+/// Excludes always take precedence over includes: a path matching any exclude glob is rejected
+/// even if it also matches an include glob or extension. `-e/--extensions` is sugar that
+/// desugars into an include glob of the form `**/*.<ext>`, so existing invocations keep working
+/// unchanged and can be freely mixed with `--include`.
+#[derive(Clone)]
+pub struct PathFilter {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl PathFilter {
+    /// Builds a filter from the legacy `extensions` list plus `--include`/`--exclude` glob
+    /// patterns. `extensions` desugars into `**/*.<ext>` include globs before compiling.
+    pub fn new(
+        extensions: &Option<Vec<&OsStr>>,
+        include: &[String],
+        exclude: &[String],
+    ) -> Result<Self> {
+        let extension_globs: Vec<String> = extensions
+            .iter()
+            .flatten()
+            .map(|ext| format!("**/*.{}", ext.to_string_lossy()))
+            .collect();
+
+        let include_patterns: Vec<&str> = extension_globs
+            .iter()
+            .map(String::as_str)
+            .chain(include.iter().map(String::as_str))
+            .collect();
+        let exclude_patterns: Vec<&str> = exclude.iter().map(String::as_str).collect();
+
+        Ok(Self {
+            include: build_glob_set(&include_patterns)?,
+            exclude: build_glob_set(&exclude_patterns)?,
+        })
+    }
+
+    /// An empty filter that matches every path, equivalent to no `--extensions`/`--include`/
+    /// `--exclude` having been given.
+    pub fn any() -> Self {
+        Self {
+            include: None,
+            exclude: None,
+        }
+    }
+
+    /// Returns whether `path` should be processed: it must not match any exclude glob, and - if
+    /// any include globs (or extensions) were given - must match at least one of them.
+    pub fn matches(&self, path: &Path) -> bool {
+        if let Some(exclude) = &self.exclude {
+            if exclude.is_match(path) {
+                return false;
+            }
+        }
+
+        match &self.include {
+            Some(include) => include.is_match(path),
+            None => true,
+        }
+    }
+}
+
+fn build_glob_set(patterns: &[&str]) -> Result<Option<GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(
+            Glob::new(pattern).with_context(|| format!("Invalid glob pattern: {}", pattern))?,
+        );
+    }
+
+    Ok(Some(builder.build()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_patterns_matches_everything() {
+        let filter = PathFilter::new(&None, &[], &[]).unwrap();
+        assert!(filter.matches(Path::new("dir/file.txt")));
+    }
+
+    #[test]
+    fn test_extensions_desugar_to_include_glob() {
+        let extensions = Some(vec![OsStr::new("txt"), OsStr::new("jpg")]);
+        let filter = PathFilter::new(&extensions, &[], &[]).unwrap();
+
+        assert!(filter.matches(Path::new("dir/file.txt")));
+        assert!(filter.matches(Path::new("dir/file.jpg")));
+        assert!(!filter.matches(Path::new("dir/file.png")));
+    }
+
+    #[test]
+    fn test_include_glob() {
+        let filter =
+            PathFilter::new(&None, &["**/*.{mp4,mkv}".to_string()], &[]).unwrap();
+
+        assert!(filter.matches(Path::new("videos/movie.mp4")));
+        assert!(!filter.matches(Path::new("videos/movie.avi")));
+    }
+
+    #[test]
+    fn test_exclude_takes_precedence_over_include() {
+        let filter = PathFilter::new(
+            &None,
+            &["**/*.mp4".to_string()],
+            &["**/sample/**".to_string()],
+        )
+        .unwrap();
+
+        assert!(filter.matches(Path::new("videos/movie.mp4")));
+        assert!(!filter.matches(Path::new("videos/sample/movie.mp4")));
+    }
+
+    #[test]
+    fn test_exclude_without_include_still_matches_everything_else() {
+        let filter = PathFilter::new(&None, &[], &["**/sample/**".to_string()]).unwrap();
+
+        assert!(filter.matches(Path::new("videos/movie.mp4")));
+        assert!(!filter.matches(Path::new("videos/sample/movie.mp4")));
+    }
+
+    #[test]
+    fn test_invalid_glob_is_an_error() {
+        let result = PathFilter::new(&None, &["[".to_string()], &[]);
+        assert!(result.is_err());
+    }
+}